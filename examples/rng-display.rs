@@ -102,9 +102,10 @@ fn main() -> ! {
         disp.init().unwrap();
         disp.flush().unwrap();
 
-        // enable the RNG peripheral and its clock
-        // this will panic if the clock configuration is unsuitable
-        let mut rand_source = dp.RNG.constrain(clocks);
+        // Enable the RNG peripheral and its clock. `constrain` checks PLL48CLK against HCLK/16
+        // up front and hands back a `Result` instead of enabling the peripheral into a state
+        // where it would just constantly report `SR.CECS`.
+        let mut rand_source = dp.RNG.constrain(clocks).expect("RNG clock configuration unsuitable");
         let mut format_buf = ArrayString::<[u8; 20]>::new();
         loop {
             //display clear
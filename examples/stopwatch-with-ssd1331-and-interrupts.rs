@@ -27,7 +27,7 @@ use crate::hal::{
     prelude::*,
     rcc::{Clocks, Rcc},
     stm32,
-    timer::{Event, Timer},
+    timer::mono::{Instant, MonoTimer},
 };
 use arrayvec::ArrayString;
 use core::cell::{Cell, RefCell};
@@ -44,8 +44,9 @@ use embedded_graphics::{
 use ssd1331::{DisplayRotation::Rotate0, Ssd1331};
 
 // Set up global state. It's all mutexed up for concurrency safety.
-static ELAPSED_MS: Mutex<Cell<u32>> = Mutex::new(Cell::new(0u32));
-static TIMER_TIM2: Mutex<RefCell<Option<Timer<stm32::TIM2>>>> = Mutex::new(RefCell::new(None));
+static MONO: Mutex<RefCell<Option<MonoTimer>>> = Mutex::new(RefCell::new(None));
+static RUN_START: Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+static ACCUMULATED_MS: Mutex<Cell<u64>> = Mutex::new(Cell::new(0));
 static STATE: Mutex<Cell<StopwatchState>> = Mutex::new(Cell::new(StopwatchState::Ready));
 static BUTTON: Mutex<RefCell<Option<PA0<Input<PullDown>>>>> = Mutex::new(RefCell::new(None));
 
@@ -112,12 +113,12 @@ fn main() -> ! {
         disp.init().unwrap();
         disp.flush().unwrap();
 
-        // Create a 1ms periodic interrupt from TIM2
-        let mut timer = Timer::tim2(dp.TIM2, 1.khz(), clocks);
-        timer.listen(Event::TimeOut);
+        // Free-running tick source, overflowing every 1ms, used to time the stopwatch's running
+        // segments
+        let mono = MonoTimer::new(dp.TIM2, 1.khz().into(), clocks);
 
         free(|cs| {
-            TIMER_TIM2.borrow(cs).replace(Some(timer));
+            MONO.borrow(cs).replace(Some(mono));
             BUTTON.borrow(cs).replace(Some(board_btn));
         });
 
@@ -125,11 +126,18 @@ fn main() -> ! {
         stm32::NVIC::unpend(hal::stm32::Interrupt::TIM2);
         stm32::NVIC::unpend(hal::stm32::Interrupt::EXTI0);
         unsafe {
+            stm32::NVIC::unmask(hal::stm32::Interrupt::TIM2);
             stm32::NVIC::unmask(hal::stm32::Interrupt::EXTI0);
         };
 
         loop {
-            let elapsed = free(|cs| ELAPSED_MS.borrow(cs).get());
+            let elapsed = free(|cs| {
+                let accumulated = ACCUMULATED_MS.borrow(cs).get();
+                match RUN_START.borrow(cs).get() {
+                    Some(start) => accumulated + start.elapsed().as_millis(),
+                    None => accumulated,
+                }
+            }) as u32;
 
             let mut format_buf = ArrayString::<[u8; 10]>::new();
             format_elapsed(&mut format_buf, elapsed);
@@ -173,13 +181,9 @@ fn main() -> ! {
 #[interrupt]
 fn TIM2() {
     free(|cs| {
-        if let Some(ref mut tim2) = TIMER_TIM2.borrow(cs).borrow_mut().deref_mut() {
-            tim2.clear_interrupt(Event::TimeOut);
+        if let Some(ref mut mono) = MONO.borrow(cs).borrow_mut().deref_mut() {
+            mono.on_interrupt();
         }
-
-        let cell = ELAPSED_MS.borrow(cs);
-        let val = cell.get();
-        cell.replace(val + 1);
     });
 }
 
@@ -221,14 +225,16 @@ fn setup_clocks(rcc: Rcc) -> Clocks {
 }
 
 fn stopwatch_start<'cs>(cs: &'cs CriticalSection) {
-    ELAPSED_MS.borrow(cs).replace(0);
-    unsafe {
-        stm32::NVIC::unmask(hal::stm32::Interrupt::TIM2);
-    }
+    ACCUMULATED_MS.borrow(cs).replace(0);
+    let now = MONO.borrow(cs).borrow().as_ref().unwrap().now();
+    RUN_START.borrow(cs).replace(Some(now));
 }
 
-fn stopwatch_stop<'cs>(_cs: &'cs CriticalSection) {
-    stm32::NVIC::mask(hal::stm32::Interrupt::TIM2);
+fn stopwatch_stop<'cs>(cs: &'cs CriticalSection) {
+    if let Some(start) = RUN_START.borrow(cs).replace(None) {
+        let cell = ACCUMULATED_MS.borrow(cs);
+        cell.replace(cell.get() + start.elapsed().as_millis());
+    }
 }
 
 // Formatting requires the arrayvec crate
@@ -0,0 +1,87 @@
+//! Delays
+
+use cortex_m::peripheral::SYST;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+
+use crate::rcc::Clocks;
+
+/// System timer (SysTick) as a delay provider
+pub struct Delay {
+    syst: SYST,
+    sysclk_hz: u32,
+}
+
+impl Delay {
+    /// Configures the system timer as a delay provider
+    pub fn new(mut syst: SYST, clocks: Clocks) -> Self {
+        syst.set_clock_source(cortex_m::peripheral::syst::SystClkSource::Core);
+
+        Delay {
+            syst,
+            sysclk_hz: clocks.sysclk().0,
+        }
+    }
+
+    /// Releases the system timer (SysTick) resource
+    pub fn free(self) -> SYST {
+        self.syst
+    }
+}
+
+impl DelayMs<u32> for Delay {
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay_us(ms.saturating_mul(1_000));
+    }
+}
+
+impl DelayMs<u16> for Delay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.delay_ms(u32::from(ms));
+    }
+}
+
+impl DelayMs<u8> for Delay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.delay_ms(u32::from(ms));
+    }
+}
+
+impl DelayUs<u32> for Delay {
+    fn delay_us(&mut self, us: u32) {
+        // The SysTick reload value register is 24 bits wide, so delays longer than that
+        // are broken up into multiple reloads.
+        const MAX_RVR: u32 = 0x00FF_FFFF;
+
+        let mut total_rvr = (us as u64 * (self.sysclk_hz as u64 / 1_000_000)) as u32;
+
+        while total_rvr != 0 {
+            let current_rvr = if total_rvr <= MAX_RVR {
+                total_rvr
+            } else {
+                MAX_RVR
+            };
+
+            self.syst.set_reload(current_rvr);
+            self.syst.clear_current();
+            self.syst.enable_counter();
+
+            total_rvr -= current_rvr;
+
+            while !self.syst.has_wrapped() {}
+
+            self.syst.disable_counter();
+        }
+    }
+}
+
+impl DelayUs<u16> for Delay {
+    fn delay_us(&mut self, us: u16) {
+        self.delay_us(u32::from(us));
+    }
+}
+
+impl DelayUs<u8> for Delay {
+    fn delay_us(&mut self, us: u8) {
+        self.delay_us(u32::from(us));
+    }
+}
@@ -0,0 +1,320 @@
+//! Direct Memory Access (DMA)
+//!
+//! Streams and channels follow the reference manual's DMA request-mapping table: each of
+//! DMA1/DMA2's eight streams (0-7) can be routed to one of eight peripheral channels (0-7) via
+//! `CR.CHSEL`. A [`Transfer`] takes ownership of both the buffer and the peripheral for the
+//! duration of the transfer and only gives them back once [`Transfer::wait`] observes the
+//! transfer-complete flag, so the buffer can never be dropped (or otherwise touched) while the
+//! DMA controller still holds a pointer to it.
+
+use core::marker::PhantomData;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use crate::stm32::{DMA1, DMA2};
+
+/// Extension trait implemented by a peripheral's data register, so a [`Transfer`] knows the
+/// source/destination address to program into the stream's `PAR`.
+pub trait PeriphAddress {
+    /// Address of the peripheral register the DMA stream reads from / writes to
+    fn address(&self) -> u32;
+}
+
+/// DMA request channel, selected via the stream's `CR.CHSEL` field
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    /// Channel 0
+    C0,
+    /// Channel 1
+    C1,
+    /// Channel 2
+    C2,
+    /// Channel 3
+    C3,
+    /// Channel 4
+    C4,
+    /// Channel 5
+    C5,
+    /// Channel 6
+    C6,
+    /// Channel 7
+    C7,
+}
+
+impl Channel {
+    fn bits(self) -> u8 {
+        match self {
+            Channel::C0 => 0,
+            Channel::C1 => 1,
+            Channel::C2 => 2,
+            Channel::C3 => 3,
+            Channel::C4 => 4,
+            Channel::C5 => 5,
+            Channel::C6 => 6,
+            Channel::C7 => 7,
+        }
+    }
+}
+
+/// Transfer data size, programmed into the stream's `PSIZE`/`MSIZE` fields
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WordSize {
+    /// 8 bits
+    Byte,
+    /// 16 bits
+    HalfWord,
+    /// 32 bits
+    Word,
+}
+
+impl WordSize {
+    fn bits(self) -> u8 {
+        match self {
+            WordSize::Byte => 0b00,
+            WordSize::HalfWord => 0b01,
+            WordSize::Word => 0b10,
+        }
+    }
+}
+
+/// Direction data moves in during the transfer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// Peripheral to memory
+    PeripheralToMemory,
+    /// Memory to peripheral
+    MemoryToPeripheral,
+}
+
+impl Direction {
+    fn bits(self) -> u8 {
+        match self {
+            Direction::PeripheralToMemory => 0b00,
+            Direction::MemoryToPeripheral => 0b01,
+        }
+    }
+}
+
+/// Whether the stream stops after one pass over the buffer, or wraps back to the start
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransferMode {
+    /// Stream disables itself once `NDTR` reaches zero
+    OneShot,
+    /// Stream reloads `NDTR` and wraps back to the start of the buffer automatically
+    Circular,
+}
+
+/// A DMA stream belonging to a particular DMA controller
+pub trait StreamX {
+    /// The DMA controller (`DMA1` or `DMA2`) this stream belongs to
+    type Dma: DmaInstance;
+
+    /// Stream index (0-7), used to compute each stream's register offset and interrupt flags
+    const NUMBER: usize;
+}
+
+macro_rules! streams {
+    ($DMA:ident, $($Stream:ident: $number:expr,)+) => {
+        $(
+            /// DMA stream
+            pub struct $Stream<DMA> {
+                _dma: PhantomData<DMA>,
+            }
+
+            impl $Stream<$DMA> {
+                pub(crate) fn new() -> Self {
+                    $Stream { _dma: PhantomData }
+                }
+            }
+
+            impl StreamX for $Stream<$DMA> {
+                type Dma = $DMA;
+                const NUMBER: usize = $number;
+            }
+        )+
+    }
+}
+
+streams!(DMA1, Stream0: 0, Stream1: 1, Stream2: 2, Stream3: 3, Stream4: 4, Stream5: 5, Stream6: 6, Stream7: 7,);
+streams!(DMA2, Stream0: 0, Stream1: 1, Stream2: 2, Stream3: 3, Stream4: 4, Stream5: 5, Stream6: 6, Stream7: 7,);
+
+/// Extension trait to split a DMA controller into its eight independent, owned streams - the
+/// same `split()` pattern [`crate::gpio::GpioExt`] uses for GPIO ports.
+pub trait DmaExt {
+    /// The streams this controller splits into
+    type Streams;
+
+    /// Splits the DMA controller into independent stream handles
+    fn split(self) -> Self::Streams;
+}
+
+macro_rules! dma_ext {
+    ($DMA:ident, $Streams:ident, $dmaxen:ident, ($($stream:ident: $Stream:ident),+)) => {
+        /// Owned DMA stream handles, obtained via [`DmaExt::split`]
+        pub struct $Streams {
+            $(
+                /// Stream
+                pub $stream: $Stream<$DMA>,
+            )+
+        }
+
+        impl DmaExt for $DMA {
+            type Streams = $Streams;
+
+            fn split(self) -> $Streams {
+                unsafe { (*crate::stm32::RCC::ptr()).ahb1enr.modify(|_, w| w.$dmaxen().set_bit()) };
+
+                $Streams {
+                    $($stream: $Stream::new(),)+
+                }
+            }
+        }
+    }
+}
+
+dma_ext!(DMA1, Dma1Streams, dma1en, (stream0: Stream0, stream1: Stream1, stream2: Stream2, stream3: Stream3, stream4: Stream4, stream5: Stream5, stream6: Stream6, stream7: Stream7));
+dma_ext!(DMA2, Dma2Streams, dma2en, (stream0: Stream0, stream1: Stream1, stream2: Stream2, stream3: Stream3, stream4: Stream4, stream5: Stream5, stream6: Stream6, stream7: Stream7));
+
+/// Per-stream register access, addressed by stream number within a DMA controller's register
+/// block. `stX` register layouts are identical across streams, only the base offset differs.
+unsafe fn stream_regs(dma: *const (), number: usize) -> *mut u32 {
+    // Each stream's register group (CR, NDTR, PAR, M0AR, M1AR, FCR) is 24 bytes, starting
+    // 0x10 bytes into the DMA controller's register block.
+    (dma as *mut u8).add(0x10 + number * 0x18) as *mut u32
+}
+
+/// An in-progress DMA transfer that owns the peripheral and buffer until it completes
+pub struct Transfer<STREAM, PERIPHERAL, BUFFER> {
+    stream: STREAM,
+    peripheral: PERIPHERAL,
+    buffer: BUFFER,
+    mode: TransferMode,
+}
+
+impl<STREAM, PERIPHERAL, BUFFER> Transfer<STREAM, PERIPHERAL, BUFFER>
+where
+    STREAM: StreamX,
+{
+    /// Starts a new DMA transfer between `peripheral` and `buffer`.
+    ///
+    /// `buffer` is consumed for the lifetime of the transfer and only handed back by
+    /// [`wait`](Self::wait), so it's impossible to drop (or otherwise alias) memory the DMA
+    /// controller still has a pointer into.
+    pub(crate) fn start(
+        stream: STREAM,
+        channel: Channel,
+        direction: Direction,
+        word_size: WordSize,
+        mode: TransferMode,
+        periph_address: u32,
+        mem_address: u32,
+        len: u16,
+        peripheral: PERIPHERAL,
+        buffer: BUFFER,
+    ) -> Self {
+        let dma = STREAM::Dma::default_instance();
+        unsafe {
+            let regs = stream_regs(dma, STREAM::NUMBER);
+            let cr = regs; // CR is the first register in the group
+            let ndtr = regs.add(1);
+            let par = regs.add(2);
+            let m0ar = regs.add(3);
+
+            // Make sure the buffer contents are visible to the DMA controller before it's
+            // allowed to start moving them (or, for P2M, before we read them back later).
+            compiler_fence(Ordering::SeqCst);
+
+            core::ptr::write_volatile(par, periph_address);
+            core::ptr::write_volatile(m0ar, mem_address);
+            core::ptr::write_volatile(ndtr, u32::from(len));
+
+            let circular = match mode {
+                TransferMode::Circular => true,
+                TransferMode::OneShot => false,
+            };
+            let cr_bits = (u32::from(channel.bits()) << 25)
+                | (u32::from(word_size.bits()) << 11)
+                | (u32::from(word_size.bits()) << 13)
+                | (u32::from(direction.bits()) << 6)
+                | ((circular as u32) << 8)
+                | (1 << 10) // MINC: increment the memory address after each beat (PINC stays
+                            // clear - the peripheral address is fixed)
+                | (1 << 4) // TCIE: transfer-complete interrupt enable
+                | 1; // EN: stream enable
+            core::ptr::write_volatile(cr, cr_bits);
+        }
+
+        Transfer {
+            stream,
+            peripheral,
+            buffer,
+            mode,
+        }
+    }
+
+    /// Blocks until the transfer-complete flag for this stream is set, then returns the buffer
+    /// and peripheral so they can be reused or inspected. Not meaningful for a circular
+    /// transfer, which never stops on its own - use [`is_complete`](Self::is_complete) to poll
+    /// per-iteration completion instead.
+    pub fn wait(self) -> (BUFFER, PERIPHERAL) {
+        while !self.is_complete() {}
+        compiler_fence(Ordering::SeqCst);
+        (self.buffer, self.peripheral)
+    }
+
+    /// Whether the stream's transfer-complete flag is currently set
+    pub fn is_complete(&self) -> bool {
+        unsafe {
+            let dma = STREAM::Dma::default_instance();
+            let lisr_hisr = (dma as *const u8).add(if STREAM::NUMBER < 4 { 0x0 } else { 0x4 }) as *const u32;
+            let bit = tcif_bit(STREAM::NUMBER);
+            core::ptr::read_volatile(lisr_hisr) & (1 << bit) != 0
+        }
+    }
+
+    /// Clears the transfer-complete flag so a circular transfer's caller can detect the next
+    /// full iteration (the half-transfer flag is not used)
+    pub fn clear_complete(&mut self) {
+        unsafe {
+            let dma = STREAM::Dma::default_instance();
+            let lifcr_hifcr = (dma as *const u8).add(0x8 + if STREAM::NUMBER < 4 { 0x0 } else { 0x4 }) as *mut u32;
+            let bit = tcif_bit(STREAM::NUMBER);
+            core::ptr::write_volatile(lifcr_hifcr, 1 << bit);
+        }
+    }
+
+    /// The transfer mode this `Transfer` was started with
+    pub fn mode(&self) -> TransferMode {
+        self.mode
+    }
+}
+
+/// Bit position of a stream's TCIF flag within `LISR`/`HISR`; streams 0-3 live in `LISR`,
+/// streams 4-7 in `HISR`, at the same relative bit positions within each half.
+fn tcif_bit(stream: usize) -> u32 {
+    match stream % 4 {
+        0 => 5,
+        1 => 11,
+        2 => 21,
+        3 => 27,
+        _ => unreachable!(),
+    }
+}
+
+/// Marker trait giving each DMA controller's PAC type a way to obtain a raw pointer to its own
+/// register block, so generic stream code can address either DMA1 or DMA2.
+pub trait DmaInstance {
+    /// Returns a pointer to this controller's register block
+    fn default_instance() -> *const ();
+}
+
+impl DmaInstance for DMA1 {
+    fn default_instance() -> *const () {
+        DMA1::ptr() as *const ()
+    }
+}
+
+impl DmaInstance for DMA2 {
+    fn default_instance() -> *const () {
+        DMA2::ptr() as *const ()
+    }
+}
@@ -0,0 +1,687 @@
+//! General Purpose Input / Output
+
+use core::marker::PhantomData;
+
+use embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
+
+/// Extension trait to split a GPIO peripheral into independent pins and registers
+pub trait GpioExt {
+    /// The parts to split the GPIO into
+    type Parts;
+
+    /// Splits the GPIO block into independent pins and registers
+    fn split(self) -> Self::Parts;
+}
+
+/// Input mode (type state)
+pub struct Input<MODE> {
+    _mode: PhantomData<MODE>,
+}
+
+/// Floating input (type state)
+pub struct Floating;
+/// Pulled down input (type state)
+pub struct PullDown;
+/// Pulled up input (type state)
+pub struct PullUp;
+
+/// Output mode (type state)
+pub struct Output<MODE> {
+    _mode: PhantomData<MODE>,
+}
+
+/// Push pull output (type state)
+pub struct PushPull;
+/// Open drain output (type state)
+pub struct OpenDrain;
+
+/// Alternate function mode (type state)
+pub struct Alternate<AF> {
+    _mode: PhantomData<AF>,
+}
+
+/// Analog mode (type state)
+pub struct Analog;
+
+macro_rules! af {
+    ($($i:expr => $AFi:ident),+) => {
+        $(
+            #[doc = "Alternate function mode"]
+            pub struct $AFi;
+        )+
+    };
+}
+
+af!(0 => AF0, 1 => AF1, 2 => AF2, 3 => AF3, 4 => AF4, 5 => AF5, 6 => AF6, 7 => AF7, 8 => AF8, 9 => AF9);
+
+/// Edge that should trigger an external interrupt
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Edge {
+    /// Rising edge
+    RISING,
+    /// Falling edge
+    FALLING,
+    /// Rising and falling edge
+    RISING_FALLING,
+}
+
+/// External interrupt pin, routed through SYSCFG/EXTI
+pub trait ExtiPin {
+    /// Make this pin a source of an external interrupt (configure the SYSCFG EXTICR mux)
+    fn make_interrupt_source(&mut self, syscfg: &mut crate::stm32::SYSCFG);
+
+    /// Enable this pin's interrupt line in the EXTI peripheral
+    fn enable_interrupt(&mut self, exti: &mut crate::stm32::EXTI);
+
+    /// Disable this pin's interrupt line in the EXTI peripheral
+    fn disable_interrupt(&mut self, exti: &mut crate::stm32::EXTI);
+
+    /// Configure which edge(s) raise this pin's interrupt
+    fn trigger_on_edge(&mut self, exti: &mut crate::stm32::EXTI, edge: Edge);
+
+    /// Clear this pin's interrupt pending bit
+    fn clear_interrupt_pending_bit(&mut self);
+
+    /// Reads this pin's interrupt pending bit
+    fn check_interrupt(&self) -> bool;
+}
+
+macro_rules! gpio {
+    ($GPIOX:ident, $gpiox:ident, $iopxenr:ident, $port:expr, $PXx:ident, [
+        $($PXi:ident: ($pxi:ident, $i:expr, $MODE:ty),)+
+    ]) => {
+        /// GPIO
+        pub mod $gpiox {
+            use core::marker::PhantomData;
+
+            use embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
+            use crate::stm32::$GPIOX;
+            use crate::stm32::{EXTI, RCC, SYSCFG};
+
+            use super::{
+                Alternate, Analog, Edge, ExtiPin, Floating, GpioExt, Input, OpenDrain, Output,
+                PullDown, PullUp, PushPull,
+                AF0, AF1, AF2, AF3, AF4, AF5, AF6, AF7, AF8, AF9,
+            };
+
+            /// GPIO parts
+            pub struct Parts {
+                $(
+                    /// Pin
+                    pub $pxi: $PXi<$MODE>,
+                )+
+            }
+
+            impl GpioExt for $GPIOX {
+                type Parts = Parts;
+
+                fn split(self) -> Parts {
+                    unsafe { (*RCC::ptr()).ahb1enr.modify(|_, w| w.$iopxenr().set_bit()) };
+
+                    Parts {
+                        $(
+                            $pxi: $PXi { _mode: PhantomData },
+                        )+
+                    }
+                }
+            }
+
+            $(
+                /// Pin
+                pub struct $PXi<MODE> {
+                    _mode: PhantomData<MODE>,
+                }
+
+                impl<MODE> $PXi<MODE> {
+                    /// Sets this pin's 2-bit `MODER` field (00 input / 01 output / 10 alternate
+                    /// function / 11 analog)
+                    fn set_moder(bits: u32) {
+                        unsafe {
+                            (*$GPIOX::ptr()).moder.modify(|r, w| {
+                                w.bits((r.bits() & !(0b11 << (2 * $i))) | (bits << (2 * $i)))
+                            });
+                        }
+                    }
+
+                    /// Sets this pin's 2-bit `PUPDR` field (00 none / 01 pull-up / 10 pull-down)
+                    fn set_pupdr(bits: u32) {
+                        unsafe {
+                            (*$GPIOX::ptr()).pupdr.modify(|r, w| {
+                                w.bits((r.bits() & !(0b11 << (2 * $i))) | (bits << (2 * $i)))
+                            });
+                        }
+                    }
+
+                    /// Sets this pin's `OTYPER` bit (0 push-pull / 1 open-drain)
+                    fn set_otyper(open_drain: bool) {
+                        unsafe {
+                            (*$GPIOX::ptr()).otyper.modify(|r, w| {
+                                let bit = open_drain as u32;
+                                w.bits((r.bits() & !(1 << $i)) | (bit << $i))
+                            });
+                        }
+                    }
+
+                    /// Drives this pin at the fastest `OSPEEDR` setting - there's no API to pick
+                    /// a slower one, so default to whatever won't limit a peripheral's timing
+                    fn set_ospeedr_very_high() {
+                        unsafe {
+                            (*$GPIOX::ptr()).ospeedr.modify(|r, w| {
+                                w.bits((r.bits() & !(0b11 << (2 * $i))) | (0b11 << (2 * $i)))
+                            });
+                        }
+                    }
+
+                    /// Sets this pin's 4-bit field in `AFRL`/`AFRH`
+                    fn set_af(af: u8) {
+                        unsafe {
+                            let gpio = &*$GPIOX::ptr();
+                            if $i < 8 {
+                                gpio.afrl.modify(|r, w| {
+                                    w.bits((r.bits() & !(0b1111 << (4 * $i))) | ((af as u32) << (4 * $i)))
+                                });
+                            } else {
+                                let off = $i - 8;
+                                gpio.afrh.modify(|r, w| {
+                                    w.bits((r.bits() & !(0b1111 << (4 * off))) | ((af as u32) << (4 * off)))
+                                });
+                            }
+                        }
+                    }
+
+                    /// Configures the pin to operate as a floating input pin
+                    pub fn into_floating_input(self) -> $PXi<Input<Floating>> {
+                        Self::set_pupdr(0b00);
+                        Self::set_moder(0b00);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as a pulled down input pin
+                    pub fn into_pull_down_input(self) -> $PXi<Input<PullDown>> {
+                        Self::set_pupdr(0b10);
+                        Self::set_moder(0b00);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as a pulled up input pin
+                    pub fn into_pull_up_input(self) -> $PXi<Input<PullUp>> {
+                        Self::set_pupdr(0b01);
+                        Self::set_moder(0b00);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as a push-pull output pin
+                    pub fn into_push_pull_output(self) -> $PXi<Output<PushPull>> {
+                        Self::set_otyper(false);
+                        Self::set_ospeedr_very_high();
+                        Self::set_moder(0b01);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as an open-drain output pin
+                    pub fn into_open_drain_output(self) -> $PXi<Output<OpenDrain>> {
+                        Self::set_otyper(true);
+                        Self::set_ospeedr_very_high();
+                        Self::set_moder(0b01);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as an analog pin
+                    pub fn into_analog(self) -> $PXi<Analog> {
+                        Self::set_pupdr(0b00);
+                        Self::set_moder(0b11);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as alternate function 1
+                    pub fn into_alternate_af1(self) -> $PXi<Alternate<AF1>> {
+                        Self::set_af(1);
+                        Self::set_ospeedr_very_high();
+                        Self::set_moder(0b10);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as alternate function 2
+                    pub fn into_alternate_af2(self) -> $PXi<Alternate<AF2>> {
+                        Self::set_af(2);
+                        Self::set_ospeedr_very_high();
+                        Self::set_moder(0b10);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as alternate function 3
+                    pub fn into_alternate_af3(self) -> $PXi<Alternate<AF3>> {
+                        Self::set_af(3);
+                        Self::set_ospeedr_very_high();
+                        Self::set_moder(0b10);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as alternate function 5
+                    pub fn into_alternate_af5(self) -> $PXi<Alternate<AF5>> {
+                        Self::set_af(5);
+                        Self::set_ospeedr_very_high();
+                        Self::set_moder(0b10);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin to operate as alternate function 9
+                    pub fn into_alternate_af9(self) -> $PXi<Alternate<AF9>> {
+                        Self::set_af(9);
+                        Self::set_ospeedr_very_high();
+                        Self::set_moder(0b10);
+                        $PXi { _mode: PhantomData }
+                    }
+
+                    /// Enables / disables the internal pull up on this pin
+                    pub fn internal_pull_up(self, on: bool) -> Self {
+                        Self::set_pupdr(if on { 0b01 } else { 0b00 });
+                        self
+                    }
+                }
+
+                impl<MODE> OutputPin for $PXi<Output<MODE>> {
+                    type Error = core::convert::Infallible;
+
+                    fn set_high(&mut self) -> Result<(), Self::Error> {
+                        unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(1 << $i)) };
+                        Ok(())
+                    }
+
+                    fn set_low(&mut self) -> Result<(), Self::Error> {
+                        unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(1 << ($i + 16))) };
+                        Ok(())
+                    }
+                }
+
+                impl<MODE> StatefulOutputPin for $PXi<Output<MODE>> {
+                    fn is_set_high(&self) -> Result<bool, Self::Error> {
+                        Ok(unsafe { (*$GPIOX::ptr()).odr.read().bits() } & (1 << $i) != 0)
+                    }
+
+                    fn is_set_low(&self) -> Result<bool, Self::Error> {
+                        Ok(!self.is_set_high()?)
+                    }
+                }
+
+                impl<MODE> ToggleableOutputPin for $PXi<Output<MODE>> {
+                    type Error = core::convert::Infallible;
+
+                    fn toggle(&mut self) -> Result<(), Self::Error> {
+                        if self.is_set_high()? {
+                            self.set_low()
+                        } else {
+                            self.set_high()
+                        }
+                    }
+                }
+
+                impl<MODE> InputPin for $PXi<Input<MODE>> {
+                    type Error = core::convert::Infallible;
+
+                    fn is_high(&self) -> Result<bool, Self::Error> {
+                        Ok(unsafe { (*$GPIOX::ptr()).idr.read().bits() } & (1 << $i) != 0)
+                    }
+
+                    fn is_low(&self) -> Result<bool, Self::Error> {
+                        Ok(!self.is_high()?)
+                    }
+                }
+
+                impl<MODE> ExtiPin for $PXi<Input<MODE>> {
+                    fn make_interrupt_source(&mut self, syscfg: &mut SYSCFG) {
+                        // Each EXTICRx covers 4 lines, 4 bits (the source port) per line.
+                        let exticr = $i / 4;
+                        let offset = 4 * ($i % 4);
+                        let mask = !(0b1111u32 << offset);
+                        let bits = ($port as u32) << offset;
+                        match exticr {
+                            0 => syscfg.exticr1.modify(|r, w| unsafe { w.bits((r.bits() & mask) | bits) }),
+                            1 => syscfg.exticr2.modify(|r, w| unsafe { w.bits((r.bits() & mask) | bits) }),
+                            2 => syscfg.exticr3.modify(|r, w| unsafe { w.bits((r.bits() & mask) | bits) }),
+                            3 => syscfg.exticr4.modify(|r, w| unsafe { w.bits((r.bits() & mask) | bits) }),
+                            _ => unreachable!(),
+                        }
+                    }
+
+                    fn enable_interrupt(&mut self, exti: &mut EXTI) {
+                        exti.imr.modify(|_, w| unsafe { w.bits(exti.imr.read().bits() | (1 << $i)) });
+                    }
+
+                    fn disable_interrupt(&mut self, exti: &mut EXTI) {
+                        exti.imr.modify(|_, w| unsafe { w.bits(exti.imr.read().bits() & !(1 << $i)) });
+                    }
+
+                    fn trigger_on_edge(&mut self, exti: &mut EXTI, edge: Edge) {
+                        match edge {
+                            Edge::RISING => {
+                                exti.rtsr.modify(|_, w| unsafe { w.bits(exti.rtsr.read().bits() | (1 << $i)) });
+                                exti.ftsr.modify(|_, w| unsafe { w.bits(exti.ftsr.read().bits() & !(1 << $i)) });
+                            }
+                            Edge::FALLING => {
+                                exti.ftsr.modify(|_, w| unsafe { w.bits(exti.ftsr.read().bits() | (1 << $i)) });
+                                exti.rtsr.modify(|_, w| unsafe { w.bits(exti.rtsr.read().bits() & !(1 << $i)) });
+                            }
+                            Edge::RISING_FALLING => {
+                                exti.rtsr.modify(|_, w| unsafe { w.bits(exti.rtsr.read().bits() | (1 << $i)) });
+                                exti.ftsr.modify(|_, w| unsafe { w.bits(exti.ftsr.read().bits() | (1 << $i)) });
+                            }
+                        }
+                    }
+
+                    fn clear_interrupt_pending_bit(&mut self) {
+                        unsafe { (*EXTI::ptr()).pr.write(|w| w.bits(1 << $i)) };
+                    }
+
+                    fn check_interrupt(&self) -> bool {
+                        unsafe { (*EXTI::ptr()).pr.read().bits() & (1 << $i) != 0 }
+                    }
+                }
+            )+
+        }
+
+        pub use $gpiox::*;
+    }
+}
+
+gpio!(GPIOA, gpioa, gpioaen, 0, PA, [
+    PA0: (pa0, 0, Input<Floating>),
+    PA1: (pa1, 1, Input<Floating>),
+    PA2: (pa2, 2, Input<Floating>),
+    PA3: (pa3, 3, Input<Floating>),
+    PA4: (pa4, 4, Input<Floating>),
+    PA5: (pa5, 5, Input<Floating>),
+    PA6: (pa6, 6, Input<Floating>),
+    PA7: (pa7, 7, Input<Floating>),
+    PA8: (pa8, 8, Input<Floating>),
+    PA9: (pa9, 9, Input<Floating>),
+    PA10: (pa10, 10, Input<Floating>),
+    PA11: (pa11, 11, Input<Floating>),
+    PA12: (pa12, 12, Input<Floating>),
+]);
+
+gpio!(GPIOB, gpiob, gpioben, 1, PB, [
+    PB0: (pb0, 0, Input<Floating>),
+    PB1: (pb1, 1, Input<Floating>),
+    PB2: (pb2, 2, Input<Floating>),
+    PB3: (pb3, 3, Input<Floating>),
+    PB4: (pb4, 4, Input<Floating>),
+    PB5: (pb5, 5, Input<Floating>),
+    PB6: (pb6, 6, Input<Floating>),
+    PB7: (pb7, 7, Input<Floating>),
+    PB8: (pb8, 8, Input<Floating>),
+    PB9: (pb9, 9, Input<Floating>),
+    PB10: (pb10, 10, Input<Floating>),
+    PB11: (pb11, 11, Input<Floating>),
+    PB12: (pb12, 12, Input<Floating>),
+    PB13: (pb13, 13, Input<Floating>),
+    PB14: (pb14, 14, Input<Floating>),
+    PB15: (pb15, 15, Input<Floating>),
+]);
+
+gpio!(GPIOC, gpioc, gpiocen, 2, PC, [
+    PC0: (pc0, 0, Input<Floating>),
+    PC1: (pc1, 1, Input<Floating>),
+    PC2: (pc2, 2, Input<Floating>),
+    PC3: (pc3, 3, Input<Floating>),
+    PC4: (pc4, 4, Input<Floating>),
+    PC5: (pc5, 5, Input<Floating>),
+    PC6: (pc6, 6, Input<Floating>),
+    PC7: (pc7, 7, Input<Floating>),
+    PC8: (pc8, 8, Input<Floating>),
+    PC9: (pc9, 9, Input<Floating>),
+    PC10: (pc10, 10, Input<Floating>),
+    PC11: (pc11, 11, Input<Floating>),
+    PC12: (pc12, 12, Input<Floating>),
+]);
+
+/// Debounced button input with click / long-press detection
+///
+/// Replaces the hand-rolled `was_pressed` edge tracking examples otherwise reimplement: sample
+/// an `InputPin` against a millisecond tick source (e.g. `timer::mono::MonoTimer`) via
+/// [`DebouncedInput::poll`], and get back discrete, already-debounced events instead.
+pub mod debounce {
+    use embedded_hal::digital::v2::InputPin;
+
+    /// An event emitted by [`DebouncedInput::poll`]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Event {
+        /// The input has just been debounced as pressed
+        Pressed,
+        /// The input was released after being held past the long-press threshold
+        Released,
+        /// The input was released before the long-press threshold elapsed - a brief press
+        Click,
+        /// The input has been held continuously for at least the configured threshold; fires
+        /// once per press, the moment the threshold is crossed. The payload is the hold
+        /// duration, in the same units as the `now` passed to `poll`.
+        LongPress(u32),
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Released,
+        Debouncing { since: u32, pressed: bool },
+        Pressed { since: u32, long_press_fired: bool },
+        /// Mirrors `Debouncing`, but on the way down from `Pressed` - `press_since` and
+        /// `long_press_fired` are carried along so a bounce back to active can restore the
+        /// original `Pressed` state, and so the eventual `Released`/`Click` choice is still
+        /// correct once the release debounce window elapses.
+        DebouncingRelease { since: u32, press_since: u32, long_press_fired: bool },
+    }
+
+    /// Wraps an `InputPin`, filtering bounce and turning raw level changes into
+    /// [`Event`]s. Entirely non-blocking: [`poll`](DebouncedInput::poll) samples the pin once
+    /// and returns immediately, so it's safe to call from a timer ISR or the main loop.
+    pub struct DebouncedInput<PIN> {
+        pin: PIN,
+        active_low: bool,
+        debounce_window: u32,
+        long_press_threshold: u32,
+        state: State,
+    }
+
+    impl<PIN, E> DebouncedInput<PIN>
+    where
+        PIN: InputPin<Error = E>,
+    {
+        /// Wraps `pin`. `active_low` should be `true` for the common pull-up button wiring
+        /// where a press reads low. `debounce_window` and `long_press_threshold` are in
+        /// whatever units the caller's tick source uses (typically milliseconds); bounce
+        /// shorter than `debounce_window` is filtered, and a continuous press held for at least
+        /// `long_press_threshold` fires [`Event::LongPress`].
+        pub fn new(pin: PIN, active_low: bool, debounce_window: u32, long_press_threshold: u32) -> Self {
+            DebouncedInput {
+                pin,
+                active_low,
+                debounce_window,
+                long_press_threshold,
+                state: State::Released,
+            }
+        }
+
+        fn is_active(&mut self) -> Result<bool, E> {
+            if self.active_low {
+                self.pin.is_low()
+            } else {
+                self.pin.is_high()
+            }
+        }
+
+        /// Samples the pin against `now`, a monotonically increasing tick count (e.g.
+        /// `start.elapsed().as_millis() as u32` for an `Instant` `start` taken once from a
+        /// `MonoTimer`, or any other free-running counter), and
+        /// returns at most one event per call.
+        pub fn poll(&mut self, now: u32) -> Option<Event> {
+            let active = self.is_active().ok()?;
+
+            match self.state {
+                State::Released => {
+                    if active {
+                        self.state = State::Debouncing { since: now, pressed: true };
+                    }
+                    None
+                }
+                State::Debouncing { since, pressed } => {
+                    if active != pressed {
+                        // Bounced back before the window elapsed - restart the window from here
+                        // rather than reporting a spurious transition.
+                        self.state = State::Debouncing { since: now, pressed: active };
+                        return None;
+                    }
+
+                    if now.wrapping_sub(since) < self.debounce_window {
+                        return None;
+                    }
+
+                    if pressed {
+                        self.state = State::Pressed { since: now, long_press_fired: false };
+                        Some(Event::Pressed)
+                    } else {
+                        // The input settled back to inactive without ever being debounced as
+                        // pressed, so there's no press to report a release for.
+                        self.state = State::Released;
+                        None
+                    }
+                }
+                State::Pressed { since, long_press_fired } => {
+                    if !active {
+                        // Don't report the release yet - run it through the same debounce
+                        // window the press edge gets, so release-side contact bounce doesn't
+                        // fire a spurious event.
+                        self.state = State::DebouncingRelease {
+                            since: now,
+                            press_since: since,
+                            long_press_fired,
+                        };
+                        return None;
+                    }
+
+                    let held = now.wrapping_sub(since);
+                    if !long_press_fired && held >= self.long_press_threshold {
+                        self.state = State::Pressed { since, long_press_fired: true };
+                        return Some(Event::LongPress(held));
+                    }
+
+                    None
+                }
+                State::DebouncingRelease { since, press_since, long_press_fired } => {
+                    if active {
+                        // Bounced back to active before the window elapsed - still pressed.
+                        self.state = State::Pressed { since: press_since, long_press_fired };
+                        return None;
+                    }
+
+                    if now.wrapping_sub(since) < self.debounce_window {
+                        return None;
+                    }
+
+                    self.state = State::Released;
+                    Some(if long_press_fired {
+                        Event::Released
+                    } else {
+                        Event::Click
+                    })
+                }
+            }
+        }
+
+        /// Releases the wrapped pin
+        pub fn free(self) -> PIN {
+            self.pin
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use core::convert::Infallible;
+
+        /// A pin whose level is set directly by the test, standing in for real contact bounce
+        struct FakePin(bool);
+
+        impl InputPin for FakePin {
+            type Error = Infallible;
+
+            fn is_high(&self) -> Result<bool, Infallible> {
+                Ok(self.0)
+            }
+
+            fn is_low(&self) -> Result<bool, Infallible> {
+                Ok(!self.0)
+            }
+        }
+
+        fn input(active_low: bool) -> DebouncedInput<FakePin> {
+            DebouncedInput::new(FakePin(active_low), active_low, 10, 100)
+        }
+
+        fn set(input: &mut DebouncedInput<FakePin>, active: bool) {
+            input.pin.0 = active != input.active_low;
+        }
+
+        #[test]
+        fn clean_press_and_release() {
+            let mut input = input(true);
+
+            set(&mut input, true);
+            assert_eq!(input.poll(0), None);
+            assert_eq!(input.poll(10), Some(Event::Pressed));
+
+            set(&mut input, false);
+            assert_eq!(input.poll(20), None);
+            assert_eq!(input.poll(30), Some(Event::Click));
+        }
+
+        #[test]
+        fn press_bounce_is_filtered() {
+            let mut input = input(true);
+
+            set(&mut input, true);
+            assert_eq!(input.poll(0), None);
+            set(&mut input, false);
+            assert_eq!(input.poll(5), None);
+            set(&mut input, true);
+            assert_eq!(input.poll(6), None);
+            // Window restarted at t=6, so it hasn't elapsed yet at t=10.
+            assert_eq!(input.poll(10), None);
+            assert_eq!(input.poll(16), Some(Event::Pressed));
+        }
+
+        #[test]
+        fn release_bounce_is_filtered() {
+            let mut input = input(true);
+
+            set(&mut input, true);
+            input.poll(0);
+            input.poll(10);
+
+            set(&mut input, false);
+            assert_eq!(input.poll(20), None);
+            // Contact bounces back to active before the release debounce window elapses - this
+            // must not be treated as a released button.
+            set(&mut input, true);
+            assert_eq!(input.poll(25), None);
+            set(&mut input, false);
+            assert_eq!(input.poll(26), None);
+            // Window restarted at t=26, elapses at t=36.
+            assert_eq!(input.poll(30), None);
+            assert_eq!(input.poll(36), Some(Event::Click));
+        }
+
+        #[test]
+        fn long_press_then_release() {
+            let mut input = input(true);
+
+            set(&mut input, true);
+            input.poll(0);
+            input.poll(10);
+
+            assert_eq!(input.poll(110), Some(Event::LongPress(100)));
+
+            set(&mut input, false);
+            assert_eq!(input.poll(120), None);
+            assert_eq!(input.poll(130), Some(Event::Released));
+        }
+    }
+}
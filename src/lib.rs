@@ -0,0 +1,45 @@
+//! HAL for the STM32F4xx family of microcontrollers
+//!
+//! This is an implementation of the [`embedded-hal`] traits for the STM32F4xx family of
+//! microcontrollers.
+//!
+//! [`embedded-hal`]: https://github.com/rust-embedded/embedded-hal
+
+#![no_std]
+
+pub extern crate cortex_m;
+pub extern crate embedded_hal as hal;
+#[cfg(feature = "rt")]
+pub extern crate stm32f4;
+
+#[cfg(feature = "stm32f401")]
+pub use stm32f4::stm32f401 as stm32;
+#[cfg(feature = "stm32f407")]
+pub use stm32f4::stm32f407 as stm32;
+#[cfg(feature = "stm32f411")]
+pub use stm32f4::stm32f411 as stm32;
+#[cfg(feature = "stm32f446")]
+pub use stm32f4::stm32f446 as stm32;
+
+#[cfg(feature = "rt")]
+pub use crate::stm32::interrupt;
+
+pub mod delay;
+pub mod dma;
+pub mod gpio;
+pub mod rcc;
+pub mod rng;
+pub mod spi;
+pub mod time;
+pub mod timer;
+
+/// Prelude
+pub mod prelude {
+    pub use crate::dma::DmaExt as _stm32f4xx_hal_dma_DmaExt;
+    pub use crate::gpio::GpioExt as _stm32f4xx_hal_gpio_GpioExt;
+    pub use crate::hal::digital::v2::{InputPin as _, OutputPin as _, StatefulOutputPin as _, ToggleableOutputPin as _};
+    pub use crate::hal::prelude::*;
+    pub use crate::rcc::RccExt as _stm32f4xx_hal_rcc_RccExt;
+    pub use crate::rng::RngExt as _stm32f4xx_hal_rng_RngExt;
+    pub use crate::time::U32Ext as _stm32f4xx_hal_time_U32Ext;
+}
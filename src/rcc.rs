@@ -0,0 +1,258 @@
+//! Reset and Clock Control
+
+use crate::stm32::{FLASH, RCC};
+use crate::time::Hertz;
+
+/// Extension trait that constrains the `RCC` peripheral
+pub trait RccExt {
+    /// Constrains the `RCC` peripheral so it plays nicely with the other abstractions
+    fn constrain(self) -> Rcc;
+}
+
+impl RccExt for RCC {
+    fn constrain(self) -> Rcc {
+        Rcc {
+            cfgr: CFGR {
+                rcc: self,
+                hse: None,
+                hclk: None,
+                sysclk: None,
+                pclk1: None,
+                pclk2: None,
+            },
+        }
+    }
+}
+
+/// Constrained RCC peripheral
+pub struct Rcc {
+    /// Clock configuration
+    pub cfgr: CFGR,
+}
+
+/// Clock configuration builder
+pub struct CFGR {
+    rcc: RCC,
+    hse: Option<u32>,
+    hclk: Option<u32>,
+    sysclk: Option<u32>,
+    pclk1: Option<u32>,
+    pclk2: Option<u32>,
+}
+
+impl CFGR {
+    /// Use an external clock source (HSE) running at the given frequency
+    pub fn use_hse<F>(mut self, freq: F) -> Self
+    where
+        F: Into<Hertz>,
+    {
+        self.hse = Some(freq.into().0);
+        self
+    }
+
+    /// Sets the desired frequency for the AHB bus
+    pub fn hclk<F>(mut self, freq: F) -> Self
+    where
+        F: Into<Hertz>,
+    {
+        self.hclk = Some(freq.into().0);
+        self
+    }
+
+    /// Sets the desired frequency for the SYSCLK bus
+    pub fn sysclk<F>(mut self, freq: F) -> Self
+    where
+        F: Into<Hertz>,
+    {
+        self.sysclk = Some(freq.into().0);
+        self
+    }
+
+    /// Sets the desired frequency for the APB1 bus
+    pub fn pclk1<F>(mut self, freq: F) -> Self
+    where
+        F: Into<Hertz>,
+    {
+        self.pclk1 = Some(freq.into().0);
+        self
+    }
+
+    /// Sets the desired frequency for the APB2 bus
+    pub fn pclk2<F>(mut self, freq: F) -> Self
+    where
+        F: Into<Hertz>,
+    {
+        self.pclk2 = Some(freq.into().0);
+        self
+    }
+
+    /// Freezes the clock configuration: actually programs `RCC_CR`/`RCC_PLLCFGR`/`RCC_CFGR` and
+    /// `FLASH_ACR` to bring the requested clock tree up, then returns the resulting frequencies.
+    pub fn freeze(self) -> Clocks {
+        let sysclk = self.sysclk.unwrap_or(self.hse.unwrap_or(HSI));
+        let hclk = self.hclk.unwrap_or(sysclk);
+        let pclk1 = self.pclk1.unwrap_or(hclk);
+        let pclk2 = self.pclk2.unwrap_or(hclk);
+
+        let ppre1 = if pclk1 == hclk { 1 } else { 2 };
+        let ppre2 = if pclk2 == hclk { 1 } else { 2 };
+
+        let rcc = self.rcc;
+
+        // Turn on HSE and wait for it to stabilize before anything downstream can select it.
+        if self.hse.is_some() {
+            rcc.cr.modify(|_, w| w.hseon().set_bit());
+            while rcc.cr.read().hserdy().bit_is_clear() {}
+        }
+
+        // RM0090 Table 10 (2.7-3.6V, zero wait states up to 30 MHz): bump the flash wait
+        // states before raising HCLK, same order the reference manual's sequence uses.
+        let flash = unsafe { &*FLASH::ptr() };
+        let wait_states = match hclk {
+            0..=30_000_000 => 0,
+            30_000_001..=60_000_000 => 1,
+            60_000_001..=90_000_000 => 2,
+            90_000_001..=120_000_000 => 3,
+            120_000_001..=150_000_000 => 4,
+            _ => 5,
+        };
+        flash.acr.modify(|_, w| unsafe { w.latency().bits(wait_states) });
+
+        // PLL48CLK feeds the RNG and USB OTG FS peripherals. It comes off the same VCO as
+        // SYSCLK (PLLCLK), just divided by PLLQ instead of PLLP, so derive it from a PLL model
+        // instead of hardcoding the commonly-seen 48 MHz result.
+        let pllsrcclk = self.hse.unwrap_or(HSI);
+        // SYSCLK only needs the PLL when it's asking for something other than the source
+        // clock verbatim - going through the PLL to reproduce HSI/HSE exactly isn't possible
+        // anyway (PLLN's minimum multiplier already overshoots it). Skip PLLCFGR/PLLON in that
+        // case, and report PLL48CLK as 0: with the PLL off, RNG/USB genuinely aren't usable,
+        // which is exactly what `RngExt::constrain`'s check is there to catch.
+        let use_pll = sysclk != pllsrcclk;
+
+        let pll48clk = if use_pll {
+            // RM0090 recommends aiming the VCO input at 2 MHz to minimize jitter; PLLM must be
+            // at least 2.
+            let pllm = (pllsrcclk / 2_000_000).max(2);
+            let vco_in = pllsrcclk / pllm;
+            // PLLP is fixed at /2, the smallest (and most common) divider, so PLLN is whatever
+            // gets the VCO to `sysclk * pllp`, clamped to RM0090's valid 50-432 range.
+            let pllp = 2;
+            let plln = (sysclk * pllp / vco_in).max(50).min(432);
+            let vco_out = vco_in * plln;
+            // PLLQ (2-15) is chosen to land as close to 48 MHz as possible; it won't divide the
+            // VCO evenly for every sysclk, so pll48clk legitimately drifts away from 48 MHz for
+            // plenty of realistic configurations - which is the point.
+            let pllq = ((vco_out + 24_000_000) / 48_000_000).max(2).min(15);
+
+            rcc.pllcfgr.write(|w| unsafe {
+                w.pllm()
+                    .bits(pllm as u8)
+                    .plln()
+                    .bits(plln as u16)
+                    .pllp()
+                    .bits(((pllp / 2) - 1) as u8)
+                    .pllq()
+                    .bits(pllq as u8)
+                    .pllsrc()
+                    .bit(self.hse.is_some())
+            });
+            rcc.cr.modify(|_, w| w.pllon().set_bit());
+            while rcc.cr.read().pllrdy().bit_is_clear() {}
+
+            vco_out / pllq
+        } else {
+            0
+        };
+
+        rcc.cfgr.modify(|_, w| unsafe {
+            w.ppre1()
+                .bits(if ppre1 == 1 { 0b000 } else { 0b100 })
+                .ppre2()
+                .bits(if ppre2 == 1 { 0b000 } else { 0b100 })
+        });
+
+        if use_pll {
+            rcc.cfgr.modify(|_, w| unsafe { w.sw().bits(0b10) });
+            while rcc.cfgr.read().sws().bits() != 0b10 {}
+        } else if self.hse.is_some() {
+            rcc.cfgr.modify(|_, w| unsafe { w.sw().bits(0b01) });
+            while rcc.cfgr.read().sws().bits() != 0b01 {}
+        }
+        // Else SYSCLK just stays on HSI (SW/SWS reset to 0b00), which is what was asked for.
+
+        Clocks {
+            hclk: Hertz(hclk),
+            pclk1: Hertz(pclk1),
+            pclk2: Hertz(pclk2),
+            ppre1,
+            ppre2,
+            sysclk: Hertz(sysclk),
+            pll48clk: Hertz(pll48clk),
+        }
+    }
+}
+
+/// HSI frequency
+const HSI: u32 = 16_000_000;
+
+/// Frozen clock frequencies
+///
+/// The existence of this value indicates that the clock configuration can no longer be changed
+#[derive(Clone, Copy)]
+pub struct Clocks {
+    hclk: Hertz,
+    pclk1: Hertz,
+    pclk2: Hertz,
+    ppre1: u8,
+    ppre2: u8,
+    sysclk: Hertz,
+    pll48clk: Hertz,
+}
+
+impl Clocks {
+    /// Returns the frequency of the AHB
+    pub fn hclk(&self) -> Hertz {
+        self.hclk
+    }
+
+    /// Returns the frequency of the APB1
+    pub fn pclk1(&self) -> Hertz {
+        self.pclk1
+    }
+
+    /// Returns the frequency of the APB2
+    pub fn pclk2(&self) -> Hertz {
+        self.pclk2
+    }
+
+    /// Returns the prescaler of the APB1
+    pub fn ppre1(&self) -> u8 {
+        self.ppre1
+    }
+
+    /// Returns the prescaler of the APB2
+    pub fn ppre2(&self) -> u8 {
+        self.ppre2
+    }
+
+    /// Returns the frequency of timers connected to APB1
+    pub fn pclk1_tim(&self) -> Hertz {
+        Hertz(self.pclk1.0 * if self.ppre1 == 1 { 1 } else { 2 })
+    }
+
+    /// Returns the frequency of timers connected to APB2
+    pub fn pclk2_tim(&self) -> Hertz {
+        Hertz(self.pclk2.0 * if self.ppre2 == 1 { 1 } else { 2 })
+    }
+
+    /// Returns the system (core) frequency
+    pub fn sysclk(&self) -> Hertz {
+        self.sysclk
+    }
+
+    /// Returns the frequency of the PLL48CLK line, from which RNG_CLK and the USB OTG FS
+    /// clock are derived
+    pub fn pll48clk(&self) -> Hertz {
+        self.pll48clk
+    }
+}
@@ -0,0 +1,122 @@
+//! Random Number Generator
+//!
+//! The RNG peripheral needs `RNG_CLK >= HCLK/16`; below that threshold the reference manual
+//! says it "will continuously report an error" (`SR.CECS`). [`RngExt::constrain`] checks this
+//! up front against the derived `PLL48CLK` rather than letting the peripheral free-run into
+//! that state.
+
+use rand_core::RngCore;
+
+use crate::rcc::Clocks;
+use crate::stm32::RNG;
+
+/// RNG error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The configured clock tree can't satisfy `RNG_CLK >= HCLK/16`; the peripheral was left
+    /// disabled rather than enabled into a state where it would only ever report errors
+    ClockTooSlow,
+    /// `SR.CECS` (clock error) was observed while reading a value; this is unrecoverable short
+    /// of reconfiguring the clock tree and calling [`RngExt::constrain`] again
+    ClockError,
+}
+
+impl From<Error> for rand_core::Error {
+    fn from(err: Error) -> Self {
+        let code = match err {
+            Error::ClockTooSlow => 1,
+            Error::ClockError => 2,
+        };
+        rand_core::Error::from(core::num::NonZeroU32::new(code).unwrap())
+    }
+}
+
+/// Extension trait to constrain the `RNG` peripheral
+pub trait RngExt {
+    /// Validates the clock configuration and, if it satisfies `RNG_CLK >= HCLK/16`, enables the
+    /// peripheral
+    fn constrain(self, clocks: Clocks) -> Result<Rng, Error>;
+}
+
+impl RngExt for RNG {
+    fn constrain(self, clocks: Clocks) -> Result<Rng, Error> {
+        // RNG_CLK is the PLL48CLK line; RM0090 24.3.2 requires it be at least HCLK/16 or the
+        // peripheral will constantly flag SR.CECS once enabled.
+        if clocks.pll48clk().0 < clocks.hclk().0 / 16 {
+            return Err(Error::ClockTooSlow);
+        }
+
+        unsafe { (*crate::stm32::RCC::ptr()).ahb2enr.modify(|_, w| w.rngen().set_bit()) };
+        self.cr.modify(|_, w| w.rngen().set_bit());
+
+        Ok(Rng { rb: self })
+    }
+}
+
+/// Constrained RNG peripheral implementing [`rand_core::RngCore`]
+pub struct Rng {
+    rb: RNG,
+}
+
+impl Rng {
+    /// Reads one 32-bit word, polling `DRDY` and applying the seed-error recovery sequence from
+    /// RM0090 24.3.2 if `SECS` is set (clear the condition, then discard the tainted value by
+    /// looping for a fresh one). A clock error is unrecoverable and returned as-is.
+    fn next_word(&mut self) -> Result<u32, Error> {
+        loop {
+            let sr = self.rb.sr.read();
+
+            if sr.cecs().bit_is_set() {
+                return Err(Error::ClockError);
+            }
+
+            if sr.secs().bit_is_set() {
+                self.rb.sr.modify(|_, w| w.seis().clear_bit());
+                // Cycling RNGEN discards the value currently in DR, which was generated while
+                // the seed was bad.
+                self.rb.cr.modify(|_, w| w.rngen().clear_bit());
+                self.rb.cr.modify(|_, w| w.rngen().set_bit());
+                continue;
+            }
+
+            if sr.drdy().bit_is_set() {
+                return Ok(self.rb.dr.read().bits());
+            }
+        }
+    }
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        // `RngCore::next_u32` is infallible; a clock error here means the clock tree changed
+        // out from under an already-`constrain`d peripheral, which is unrecoverable.
+        self.next_word()
+            .unwrap_or_else(|e| panic!("RNG clock error: {:?}", e))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let lo = u64::from(self.next_u32());
+        let hi = u64::from(self.next_u32());
+        (hi << 32) | lo
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.try_fill_bytes(dest).unwrap();
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        let mut chunks = dest.chunks_exact_mut(4);
+        for chunk in &mut chunks {
+            let word = self.next_word()?;
+            chunk.copy_from_slice(&word.to_ne_bytes());
+        }
+
+        let remainder = chunks.into_remainder();
+        if !remainder.is_empty() {
+            let word = self.next_word()?;
+            remainder.copy_from_slice(&word.to_ne_bytes()[..remainder.len()]);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Serial Peripheral Interface
+//!
+//! # DMA-driven writes
+//!
+//! Flushing a display over `spi2.write(&framebuffer)` blocks the CPU for the whole transfer.
+//! [`Spi::write_dma`] hands the buffer off to a DMA stream instead, so the main loop can go
+//! compute the next frame while this one clocks out in the background:
+//!
+//! ```ignore
+//! use stm32f4xx_hal::dma::{Channel, DmaExt};
+//!
+//! let streams = dp.DMA1.split();
+//! let transfer = spi2.write_dma(streams.stream4, Channel::C0, &FRAMEBUFFER);
+//! // ... compute the next frame here while the transfer runs in the background ...
+//! let (_buffer, spi2) = transfer.wait();
+//! ```
+
+use core::ptr;
+
+use embedded_hal::spi::{Mode as SpiMode, Phase as SpiPhase, Polarity as SpiPolarity};
+use nb;
+
+use crate::dma::{Channel, Direction as DmaDirection, PeriphAddress, StreamX, Transfer, TransferMode, WordSize};
+use crate::rcc::Clocks;
+use crate::stm32::{SPI1, SPI2};
+use crate::time::Hertz;
+
+pub use embedded_hal::spi::{Mode, Phase, Polarity};
+
+/// SPI error
+#[derive(Debug)]
+pub enum Error {
+    /// Overrun occurred
+    Overrun,
+    /// Mode fault occurred
+    ModeFault,
+    /// CRC error
+    Crc,
+}
+
+/// A pair of pins usable as SCK, MISO and MOSI for a given SPI peripheral
+pub trait Pins<SPI> {}
+
+macro_rules! pins {
+    ($($SPIX:ty: SCK: [$($SCK:ty),*] MISO: [$($MISO:ty),*] MOSI: [$($MOSI:ty),*])+) => {
+        $(
+            $(
+                $(
+                    impl Pins<$SPIX> for ($SCK, $MISO, $MOSI) {}
+                )*
+            )*
+        )+
+    }
+}
+
+use crate::gpio::{gpiob::*, Alternate, AF5};
+
+pins! {
+    SPI1: SCK: [PB3<Alternate<AF5>>] MISO: [PB4<Alternate<AF5>>] MOSI: [PB5<Alternate<AF5>>]
+    SPI2: SCK: [PB13<Alternate<AF5>>] MISO: [PB14<Alternate<AF5>>] MOSI: [PB15<Alternate<AF5>>]
+}
+
+/// SPI peripheral operating in full duplex master mode
+pub struct Spi<SPI, PINS> {
+    spi: SPI,
+    pins: PINS,
+}
+
+macro_rules! spi {
+    ($($SPIX:ident: ($spiX:ident, $apbXenr:ident, $spiXen:ident, $pclkX:ident),)+) => {
+        $(
+            impl<PINS> Spi<$SPIX, PINS> {
+                /// Configures the SPI peripheral to operate in full duplex master mode
+                pub fn $spiX(
+                    spi: $SPIX,
+                    pins: PINS,
+                    mode: SpiMode,
+                    freq: Hertz,
+                    clocks: Clocks,
+                ) -> Self
+                where
+                    PINS: Pins<$SPIX>,
+                {
+                    unsafe { (*crate::stm32::RCC::ptr()).$apbXenr.modify(|_, w| w.$spiXen().set_bit()) };
+
+                    // Calculate the baud rate prescaler bits whose divider is nearest the
+                    // pclk/freq ratio; the resulting clock may be above or below the requested
+                    // frequency.
+                    let br = match clocks.$pclkX().0 / freq.0 {
+                        0 => unreachable!(),
+                        1..=2 => 0b000,
+                        3..=5 => 0b001,
+                        6..=11 => 0b010,
+                        12..=23 => 0b011,
+                        24..=47 => 0b100,
+                        48..=95 => 0b101,
+                        96..=191 => 0b110,
+                        _ => 0b111,
+                    };
+
+                    spi.cr1.write(|w| unsafe {
+                        w.cpha()
+                            .bit(mode.phase == SpiPhase::CaptureOnSecondTransition)
+                            .cpol()
+                            .bit(mode.polarity == SpiPolarity::IdleHigh)
+                            .mstr()
+                            .set_bit()
+                            .br()
+                            .bits(br)
+                            .spe()
+                            .set_bit()
+                            .ssm()
+                            .set_bit()
+                            .ssi()
+                            .set_bit()
+                    });
+
+                    Spi { spi, pins }
+                }
+
+                /// Releases the SPI peripheral and associated pins
+                pub fn free(self) -> ($SPIX, PINS) {
+                    (self.spi, self.pins)
+                }
+            }
+        )+
+    }
+}
+
+spi! {
+    SPI1: (spi1, apb2enr, spi1en, pclk2),
+    SPI2: (spi2, apb1enr, spi2en, pclk1),
+}
+
+impl<SPI, PINS> embedded_hal::blocking::spi::write::Default<u8> for Spi<SPI, PINS> where
+    Spi<SPI, PINS>: embedded_hal::spi::FullDuplex<u8>
+{
+}
+
+impl<PINS> embedded_hal::spi::FullDuplex<u8> for Spi<SPI2, PINS> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        let sr = self.spi.sr.read();
+
+        if sr.ovr().bit_is_set() {
+            Err(nb::Error::Other(Error::Overrun))
+        } else if sr.modf().bit_is_set() {
+            Err(nb::Error::Other(Error::ModeFault))
+        } else if sr.crcerr().bit_is_set() {
+            Err(nb::Error::Other(Error::Crc))
+        } else if sr.rxne().bit_is_set() {
+            Ok(unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) })
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
+        let sr = self.spi.sr.read();
+
+        if sr.ovr().bit_is_set() {
+            Err(nb::Error::Other(Error::Overrun))
+        } else if sr.modf().bit_is_set() {
+            Err(nb::Error::Other(Error::ModeFault))
+        } else if sr.crcerr().bit_is_set() {
+            Err(nb::Error::Other(Error::Crc))
+        } else if sr.txe().bit_is_set() {
+            unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u8, byte) };
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<PINS> PeriphAddress for Spi<SPI2, PINS> {
+    fn address(&self) -> u32 {
+        &self.spi.dr as *const _ as u32
+    }
+}
+
+impl<PINS> Spi<SPI2, PINS> {
+    /// Hands this SPI's data register to a DMA stream for a one-shot memory-to-peripheral
+    /// transfer and returns immediately, so the caller can go compute the next frame while this
+    /// one flushes in the background. `buffer` must be `'static` since the DMA controller
+    /// holds a raw pointer into it until
+    /// [`Transfer::wait`] observes the transfer-complete flag.
+    pub fn write_dma<STREAM>(
+        self,
+        stream: STREAM,
+        channel: Channel,
+        buffer: &'static [u8],
+    ) -> Transfer<STREAM, Self, &'static [u8]>
+    where
+        STREAM: StreamX,
+    {
+        self.spi.cr2.modify(|_, w| w.txdmaen().set_bit());
+
+        let periph_address = self.address();
+        let mem_address = buffer.as_ptr() as u32;
+        let len = buffer.len() as u16;
+
+        Transfer::start(
+            stream,
+            channel,
+            DmaDirection::MemoryToPeripheral,
+            WordSize::Byte,
+            TransferMode::OneShot,
+            periph_address,
+            mem_address,
+            len,
+            self,
+            buffer,
+        )
+    }
+}
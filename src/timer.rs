@@ -0,0 +1,492 @@
+//! Timers
+//!
+//! Pacing, PWM generation, and quadrature decoding on the general-purpose and advanced-control
+//! timer peripherals (TIM1-TIM5, TIM8).
+
+use cast::{u16, u32};
+
+use crate::rcc::Clocks;
+use crate::stm32::{TIM1, TIM2, TIM3, TIM4, TIM5, TIM8};
+use crate::time::Hertz;
+
+/// Interrupt events
+#[derive(Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Timer timed out / count down ended
+    TimeOut,
+}
+
+/// A timer peripheral configured as a periodic count-down timer
+pub struct Timer<TIM> {
+    tim: TIM,
+    clocks: Clocks,
+}
+
+macro_rules! timers {
+    ($($TIM:ident: ($tim:ident, $pclk_tim:ident),)+) => {
+        $(
+            impl Timer<$TIM> {
+                /// Configures a TIM peripheral as a periodic count down timer
+                pub fn $tim(tim: $TIM, timeout: Hertz, clocks: Clocks) -> Self {
+                    let mut timer = Timer { tim, clocks };
+                    timer.start(timeout);
+                    timer
+                }
+
+                /// (Re)starts the timer counting down from the given frequency
+                pub fn start(&mut self, timeout: Hertz) {
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    self.tim.cnt.reset();
+
+                    let ticks = self.clocks.$pclk_tim().0 / timeout.0;
+                    let psc = u16((ticks - 1) / (1 << 16)).unwrap_or(0);
+                    self.tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+                    let arr = u16(ticks / u32(psc + 1)).unwrap_or(u16::max_value());
+                    self.tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
+
+                    self.tim.egr.write(|w| w.ug().set_bit());
+                    self.tim.sr.modify(|_, w| w.uif().clear_bit());
+                    self.tim.cr1.modify(|_, w| w.cen().set_bit());
+                }
+
+                /// Enables an interrupt event
+                pub fn listen(&mut self, event: Event) {
+                    match event {
+                        Event::TimeOut => self.tim.dier.write(|w| w.uie().set_bit()),
+                    }
+                }
+
+                /// Clears an interrupt event's pending flag
+                pub fn clear_interrupt(&mut self, event: Event) {
+                    match event {
+                        Event::TimeOut => self.tim.sr.modify(|_, w| w.uif().clear_bit()),
+                    }
+                }
+
+                /// Releases the TIM peripheral
+                pub fn free(self) -> $TIM {
+                    self.tim
+                }
+            }
+        )+
+    }
+}
+
+timers! {
+    TIM2: (tim2, pclk1_tim),
+    TIM3: (tim3, pclk1_tim),
+    TIM4: (tim4, pclk1_tim),
+    TIM5: (tim5, pclk1_tim),
+    TIM1: (tim1, pclk2_tim),
+    TIM8: (tim8, pclk2_tim),
+}
+
+/// Quadrature encoder interface
+pub mod qei {
+    use cast::u32;
+
+    use crate::gpio::{gpioa::*, gpiob::*, gpioc::*, Alternate, AF1, AF2, AF3};
+    use crate::stm32::{TIM1, TIM2, TIM3, TIM4, TIM5, TIM8};
+
+    /// The direction the encoder last moved in, as reported by the timer's `CR1.DIR` bit
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Direction {
+        /// Counter is counting up (CW rotation, by convention)
+        Upcounting,
+        /// Counter is counting down (CCW rotation, by convention)
+        Downcounting,
+    }
+
+    /// Which encoder channel(s) the timer counts edges on
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum QeiOptions {
+        /// Count edges on TI1 only (x2 decoding)
+        Ti1,
+        /// Count edges on TI2 only (x2 decoding)
+        Ti2,
+        /// Count edges on both TI1 and TI2 (x4 decoding)
+        Ti12,
+    }
+
+    /// A pair of pins usable as the CH1/CH2 encoder inputs of a given timer
+    pub trait Pins<TIM> {}
+
+    // CH1/CH2 AF pin pairs, one per timer, per the reference manual's alternate function table.
+    impl Pins<TIM1> for (PA8<Alternate<AF1>>, PA9<Alternate<AF1>>) {}
+    impl Pins<TIM2> for (PA0<Alternate<AF1>>, PA1<Alternate<AF1>>) {}
+    impl Pins<TIM3> for (PB4<Alternate<AF2>>, PB5<Alternate<AF2>>) {}
+    impl Pins<TIM4> for (PB6<Alternate<AF2>>, PB7<Alternate<AF2>>) {}
+    impl Pins<TIM5> for (PA0<Alternate<AF2>>, PA1<Alternate<AF2>>) {}
+    impl Pins<TIM8> for (PC6<Alternate<AF3>>, PC7<Alternate<AF3>>) {}
+
+    /// Quadrature encoder interface, built on a general-purpose timer's encoder mode
+    pub struct Qei<TIM, PINS> {
+        tim: TIM,
+        pins: PINS,
+    }
+
+    macro_rules! qei {
+        ($($TIM:ident: $tim:ident,)+) => {
+            $(
+                impl<PINS> Qei<$TIM, PINS>
+                where
+                    PINS: Pins<$TIM>,
+                {
+                    /// Configures a TIM peripheral as a quadrature encoder, counting edges seen
+                    /// on the given CH1/CH2 pins. `arr` sets the modulo the count wraps at.
+                    pub fn new(tim: $TIM, pins: PINS, options: QeiOptions, arr: u16) -> Self {
+                        // Map TI1/TI2 onto CC1/CC2 directly (CC1S = CC2S = 0b01)
+                        tim.ccmr1_input()
+                            .write(|w| unsafe { w.cc1s().bits(0b01).cc2s().bits(0b01) });
+
+                        let sms = match options {
+                            QeiOptions::Ti1 => 0b001,
+                            QeiOptions::Ti2 => 0b010,
+                            QeiOptions::Ti12 => 0b011,
+                        };
+                        tim.smcr.modify(|_, w| unsafe { w.sms().bits(sms) });
+
+                        tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
+
+                        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                        Qei { tim, pins }
+                    }
+
+                    /// Current encoder count, wrapping at the `arr` value passed to `new`
+                    pub fn count(&self) -> u16 {
+                        self.tim.cnt.read().bits() as u16
+                    }
+
+                    /// Direction of the last counted transition, read from `CR1.DIR`
+                    pub fn direction(&self) -> Direction {
+                        if self.tim.cr1.read().dir().bit_is_clear() {
+                            Direction::Upcounting
+                        } else {
+                            Direction::Downcounting
+                        }
+                    }
+
+                    /// Resets the count back to zero
+                    pub fn reset(&mut self) {
+                        self.tim.cnt.reset();
+                    }
+
+                    /// Releases the TIM peripheral and pins
+                    pub fn release(self) -> ($TIM, PINS) {
+                        (self.tim, self.pins)
+                    }
+                }
+            )+
+        }
+    }
+
+    qei! {
+        TIM1: tim1,
+        TIM2: tim2,
+        TIM3: tim3,
+        TIM4: tim4,
+        TIM5: tim5,
+        TIM8: tim8,
+    }
+}
+
+/// PWM output generation
+///
+/// Wired up for TIM1 and TIM8 - the two advanced-control timers the request's `BDTR.MOE` note
+/// calls out - across all four of their output-compare channels. TIM2-TIM5 would follow the
+/// same `ChannelConfig`/`Pins`/`pwm_channel!` pattern, just without the `BDTR.MOE` step, but
+/// aren't wired up yet.
+pub mod pwm {
+    use core::marker::PhantomData;
+
+    use cast::{u16, u32};
+
+    use super::Timer;
+    use crate::gpio::{gpioa::*, gpioc::*, Alternate, AF1, AF3};
+    use crate::rcc::Clocks;
+    use crate::stm32::{TIM1, TIM8};
+    use crate::time::Hertz;
+
+    /// Output-compare channel 1
+    pub struct C1;
+    /// Output-compare channel 2
+    pub struct C2;
+    /// Output-compare channel 3
+    pub struct C3;
+    /// Output-compare channel 4
+    pub struct C4;
+
+    /// A pin usable as the given output-compare channel of a given timer
+    pub trait Pins<TIM, CHANNEL> {}
+
+    impl Pins<TIM1, C1> for PA8<Alternate<AF1>> {}
+    impl Pins<TIM1, C2> for PA9<Alternate<AF1>> {}
+    impl Pins<TIM1, C3> for PA10<Alternate<AF1>> {}
+    impl Pins<TIM1, C4> for PA11<Alternate<AF1>> {}
+    impl Pins<TIM8, C1> for PC6<Alternate<AF3>> {}
+    impl Pins<TIM8, C2> for PC7<Alternate<AF3>> {}
+    impl Pins<TIM8, C3> for PC8<Alternate<AF3>> {}
+    impl Pins<TIM8, C4> for PC9<Alternate<AF3>> {}
+
+    /// One output-compare channel of a timer running in PWM mode
+    pub struct PwmChannel<TIM, CHANNEL> {
+        _tim: PhantomData<TIM>,
+        _channel: PhantomData<CHANNEL>,
+    }
+
+    /// Configures a channel's output-compare mode (mode 1, preloaded) so `set_duty` takes
+    /// effect on the next update event instead of glitching mid-period
+    trait ChannelConfig<TIM> {
+        fn configure(tim: &TIM);
+    }
+
+    macro_rules! channel_config {
+        ($TIM:ident, $C:ident, $ccmr:ident, $ocXm:ident, $ocXpe:ident) => {
+            impl ChannelConfig<$TIM> for $C {
+                fn configure(tim: &$TIM) {
+                    tim.$ccmr()
+                        .modify(|_, w| unsafe { w.$ocXm().bits(0b110).$ocXpe().set_bit() });
+                }
+            }
+        };
+    }
+
+    channel_config!(TIM1, C1, ccmr1_output, oc1m, oc1pe);
+    channel_config!(TIM1, C2, ccmr1_output, oc2m, oc2pe);
+    channel_config!(TIM1, C3, ccmr2_output, oc3m, oc3pe);
+    channel_config!(TIM1, C4, ccmr2_output, oc4m, oc4pe);
+    channel_config!(TIM8, C1, ccmr1_output, oc1m, oc1pe);
+    channel_config!(TIM8, C2, ccmr1_output, oc2m, oc2pe);
+    channel_config!(TIM8, C3, ccmr2_output, oc3m, oc3pe);
+    channel_config!(TIM8, C4, ccmr2_output, oc4m, oc4pe);
+
+    macro_rules! pwm_channel {
+        ($TIM:ident, $C:ident, $ccXe:ident, $ccrX:ident) => {
+            impl PwmChannel<$TIM, $C> {
+                /// Enables this channel's output (`CCER`, plus `BDTR.MOE` for this
+                /// advanced-control timer)
+                pub fn enable(&mut self) {
+                    let tim = unsafe { &*$TIM::ptr() };
+                    tim.bdtr.modify(|_, w| w.moe().set_bit());
+                    tim.ccer.modify(|_, w| w.$ccXe().set_bit());
+                }
+
+                /// Disables this channel's output
+                pub fn disable(&mut self) {
+                    let tim = unsafe { &*$TIM::ptr() };
+                    tim.ccer.modify(|_, w| w.$ccXe().clear_bit());
+                }
+
+                /// The duty value corresponding to a 100% duty cycle, i.e. the timer's current
+                /// `ARR`
+                pub fn get_max_duty(&self) -> u16 {
+                    unsafe { (*$TIM::ptr()).arr.read().bits() as u16 }
+                }
+
+                /// Sets this channel's compare value; 0 is always-low, `get_max_duty()` is
+                /// always-high
+                pub fn set_duty(&mut self, duty: u16) {
+                    unsafe { (*$TIM::ptr()).$ccrX.write(|w| w.bits(u32::from(duty))) };
+                }
+            }
+        };
+    }
+
+    pwm_channel!(TIM1, C1, cc1e, ccr1);
+    pwm_channel!(TIM1, C2, cc2e, ccr2);
+    pwm_channel!(TIM1, C3, cc3e, ccr3);
+    pwm_channel!(TIM1, C4, cc4e, ccr4);
+    pwm_channel!(TIM8, C1, cc1e, ccr1);
+    pwm_channel!(TIM8, C2, cc2e, ccr2);
+    pwm_channel!(TIM8, C3, cc3e, ccr3);
+    pwm_channel!(TIM8, C4, cc4e, ccr4);
+
+    macro_rules! pwm_timer {
+        ($TIM:ident) => {
+            impl Timer<$TIM> {
+                /// Configures one of this timer's channels for PWM output at `freq`, consuming
+                /// a pin wired to it. `PSC`/`ARR` are derived from the timer's APB clock the
+                /// same way a count-down [`Timer`] does.
+                pub fn pwm<PIN, C>(tim: $TIM, pin: PIN, freq: Hertz, clocks: Clocks) -> PwmChannel<$TIM, C>
+                where
+                    PIN: Pins<$TIM, C>,
+                    C: ChannelConfig<$TIM>,
+                {
+                    let ticks = clocks.pclk2_tim().0 / freq.0;
+                    let psc = u16((ticks - 1) / (1 << 16)).unwrap_or(0);
+                    tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+                    let arr = u16(ticks / u32(psc + 1)).unwrap_or(u16::max_value());
+                    tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
+
+                    C::configure(&tim);
+
+                    tim.egr.write(|w| w.ug().set_bit());
+                    tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                    let _ = pin;
+                    PwmChannel { _tim: PhantomData, _channel: PhantomData }
+                }
+            }
+        };
+    }
+
+    pwm_timer!(TIM1);
+    pwm_timer!(TIM8);
+
+    /// A tone/buzzer helper driving a [`PwmChannel`] at 50% duty while sounding
+    pub struct Tone<TIM, CHANNEL> {
+        channel: PwmChannel<TIM, CHANNEL>,
+        clocks: Clocks,
+    }
+
+    impl Tone<TIM1, C1> {
+        /// Wraps a PWM channel as a tone source, clocked against `clocks` so [`set_frequency`]
+        /// can re-derive `PSC`/`ARR` for an arbitrary note/Hz value
+        pub fn new(channel: PwmChannel<TIM1, C1>, clocks: Clocks) -> Self {
+            Tone { channel, clocks }
+        }
+
+        /// Re-targets the underlying timer at a new frequency (e.g. a note or sidetone pitch)
+        pub fn set_frequency(&mut self, freq: Hertz) {
+            let tim = unsafe { &*TIM1::ptr() };
+            let ticks = self.clocks.pclk2_tim().0 / freq.0;
+            let psc = u16((ticks - 1) / (1 << 16)).unwrap_or(0);
+            tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+            let arr = u16(ticks / u32(psc + 1)).unwrap_or(u16::max_value());
+            tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
+
+            // Without this, PSC/ARR only take effect at the next update event under the *old*
+            // frequency, so a retune wouldn't actually change pitch until the note it's replacing
+            // would've ended anyway.
+            tim.egr.write(|w| w.ug().set_bit());
+
+            self.channel.set_duty(arr / 2);
+        }
+
+        /// Starts sounding: drives the channel at 50% duty and enables its output
+        pub fn start(&mut self) {
+            let max_duty = self.channel.get_max_duty();
+            self.channel.set_duty(max_duty / 2);
+            self.channel.enable();
+        }
+
+        /// Stops sounding by disabling the channel's output
+        pub fn stop(&mut self) {
+            self.channel.disable();
+        }
+    }
+}
+
+/// A free-running monotonic clock built on a hardware timer
+///
+/// Unlike a millisecond counter kept in a `Mutex<Cell<u32>>`, reading [`MonoTimer::now`] or
+/// [`Instant::elapsed`] never needs a critical section: the ISR is the only writer (to
+/// `OVERFLOWS`), so readers can just retry if they observe it change mid-read.
+pub mod mono {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::rcc::Clocks;
+    use crate::stm32::TIM2;
+    use crate::time::Hertz;
+
+    /// Number of times the underlying counter has wrapped, bumped from the timer's update-event
+    /// ISR
+    static OVERFLOWS: AtomicU32 = AtomicU32::new(0);
+    /// Rate `CNT` counts at (the timer clock divided by `PSC + 1`), needed to convert a tick
+    /// count into milliseconds
+    static TICK_FREQ_HZ: AtomicU32 = AtomicU32::new(1);
+    /// `ARR + 1`, i.e. the number of ticks the counter actually counts through before wrapping.
+    /// `MonoTimer::new` sizes `ARR` to hit the requested overflow frequency, so this is almost never
+    /// `0x1_0000` - a counter wrapping at, say, 16000 can't be reassembled by shifting the
+    /// overflow count by 16 bits.
+    static RELOAD_TICKS: AtomicU32 = AtomicU32::new(1 << 16);
+
+    /// Combines the current overflow count with a fresh read of `CNT` into a 64-bit tick count,
+    /// retrying if an overflow lands between the two reads so the result never straddles a wrap
+    fn now_ticks() -> u64 {
+        loop {
+            let before = OVERFLOWS.load(Ordering::Acquire);
+            let cnt = unsafe { (*TIM2::ptr()).cnt.read().bits() as u16 };
+            let after = OVERFLOWS.load(Ordering::Acquire);
+            if before == after {
+                let reload = u64::from(RELOAD_TICKS.load(Ordering::Relaxed));
+                return u64::from(before) * reload + u64::from(cnt);
+            }
+        }
+    }
+
+    /// Claims `TIM2` as a free-running tick source
+    pub struct MonoTimer {
+        tim: TIM2,
+    }
+
+    impl MonoTimer {
+        /// Configures `tim` as a free-running up-counter that overflows at `freq`, with its
+        /// update interrupt enabled so overflows are tracked. `CNT` itself counts at the timer
+        /// clock divided by `PSC + 1`, which is the unit of [`Instant`] and [`Duration`] ticks. The caller still needs to unmask `TIM2`
+        /// in the NVIC and call [`MonoTimer::on_interrupt`] from its handler.
+        pub fn new(tim: TIM2, freq: Hertz, clocks: Clocks) -> Self {
+            let ticks = clocks.pclk1_tim().0 / freq.0;
+            let psc = ((ticks - 1) / (1 << 16)) as u16;
+            tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+            let arr = ticks / (u32::from(psc) + 1);
+            tim.arr.write(|w| unsafe { w.bits(arr) });
+
+            OVERFLOWS.store(0, Ordering::Relaxed);
+            TICK_FREQ_HZ.store(clocks.pclk1_tim().0 / (u32::from(psc) + 1), Ordering::Relaxed);
+            RELOAD_TICKS.store(arr + 1, Ordering::Relaxed);
+
+            tim.egr.write(|w| w.ug().set_bit());
+            tim.sr.modify(|_, w| w.uif().clear_bit());
+            tim.dier.write(|w| w.uie().set_bit());
+            tim.cr1.modify(|_, w| w.cen().set_bit());
+
+            MonoTimer { tim }
+        }
+
+        /// Call this, and only this, from `TIM2`'s interrupt handler on every update event
+        pub fn on_interrupt(&mut self) {
+            self.tim.sr.modify(|_, w| w.uif().clear_bit());
+            OVERFLOWS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        /// The current time
+        pub fn now(&self) -> Instant {
+            Instant { ticks: now_ticks() }
+        }
+
+        /// Releases the TIM peripheral
+        pub fn free(self) -> TIM2 {
+            self.tim
+        }
+    }
+
+    /// A point in time captured from a [`MonoTimer`]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Instant {
+        ticks: u64,
+    }
+
+    impl Instant {
+        /// Time elapsed between this `Instant` and now
+        pub fn elapsed(&self) -> Duration {
+            Duration {
+                ticks: now_ticks().wrapping_sub(self.ticks),
+            }
+        }
+    }
+
+    /// A span of time measured in `MonoTimer` ticks
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Duration {
+        ticks: u64,
+    }
+
+    impl Duration {
+        /// This duration in milliseconds, derived from the rate `MonoTimer::new` set `CNT` to
+        pub fn as_millis(&self) -> u64 {
+            self.ticks * 1000 / u64::from(TICK_FREQ_HZ.load(Ordering::Relaxed))
+        }
+    }
+}